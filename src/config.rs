@@ -0,0 +1,87 @@
+/// Options controlling how a thumbnail is generated.
+///
+/// This is deliberately a plain data struct: callers (CLI front end,
+/// thumbnailer daemon, etc.) are responsible for building it however makes
+/// sense for them and handing it to [`crate::run`].
+pub struct Config {
+    pub stl_filename: String,
+    pub visible: bool,
+
+    /// Images to produce from the render, each at its own size and in its
+    /// own format. The mesh is loaded and the GL program compiled only once,
+    /// no matter how many outputs are requested. Must not be empty; the
+    /// largest one (by pixel area) is rendered directly, the rest are
+    /// resized from it. An output whose aspect ratio differs from that
+    /// render is scaled to cover its size and center-cropped, not stretched.
+    pub outputs: Vec<OutputTarget>,
+
+    /// Overlay mesh edges (wireframe) on top of the shaded model.
+    pub wireframe: bool,
+    /// How strongly the wireframe overlay is blended over the shaded color,
+    /// from 0.0 (invisible) to 1.0 (edges fully opaque). Only meaningful
+    /// when `wireframe` is set.
+    pub wireframe_blend: f32,
+
+    /// World-space camera position. Ignored if `auto_frame` is set, except
+    /// for the viewing direction (camera_position - camera_target), which is
+    /// always kept.
+    pub camera_position: [f32; 3],
+    /// Point the camera looks at.
+    pub camera_target: [f32; 3],
+    /// Camera up vector.
+    pub camera_up: [f32; 3],
+    /// Vertical field of view, in degrees. Not used for the projection if
+    /// `orthographic` is set or `projection_matrix` is `Some`; `auto_frame`
+    /// still uses it to pick the camera distance.
+    pub fov_deg: f32,
+    /// Render with an orthographic projection instead of perspective.
+    pub orthographic: bool,
+    /// Use a caller-supplied projection matrix instead of the built-in
+    /// perspective/orthographic one.
+    pub projection_matrix: Option<[[f32; 4]; 4]>,
+    /// After centering and scaling the mesh, move the camera along its
+    /// existing viewing direction so the model's bounding sphere tightly
+    /// fills the frame for the given FOV and aspect ratio.
+    pub auto_frame: bool,
+
+    /// Which antialiasing technique to render with.
+    pub antialiasing: Antialiasing,
+}
+
+/// Antialiasing technique used to smooth triangle edges.
+#[derive(Copy, Clone)]
+pub enum Antialiasing {
+    /// No antialiasing.
+    None,
+    /// Multisample antialiasing with the given number of samples per pixel
+    /// (e.g. 2, 4, 8). Sharper than FXAA, but needs GPU/driver support -
+    /// falls back to `None` if the requested sample count can't be created.
+    Msaa(u16),
+    /// Fast Approximate Antialiasing: a cheap full-screen post-process pass.
+    /// Works everywhere but blurs fine detail a bit more than MSAA.
+    Fxaa,
+}
+
+impl Antialiasing {
+    /// Multisample count to request from the GL context, or 0 for none.
+    pub fn msaa_samples(&self) -> u16 {
+        match *self {
+            Antialiasing::Msaa(samples) => samples,
+            _ => 0,
+        }
+    }
+}
+
+/// One image to write out after rendering. Several of these sharing a
+/// single render let a thumbnailer ask for every icon size it needs (e.g.
+/// 128px and 256px) without re-parsing the STL or recompiling shaders.
+pub struct OutputTarget {
+    /// Where to write the image. Writes to stdout if `None`. Only one output
+    /// in a given `outputs` list should leave this `None` - stdout isn't
+    /// seekable, so two or more would write their encoded bytes back to back
+    /// on the same stream with nothing to separate them.
+    pub filename: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub format: image::ImageFormat,
+}
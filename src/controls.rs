@@ -0,0 +1,123 @@
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, Vector3};
+
+/// Keep the camera away from the poles of its orbit (i.e. looking straight
+/// down/up `up`): right at the pole, yaw becomes meaningless and a drag can
+/// suddenly spin the view around (gimbal flip).
+const MIN_PITCH: f32 = -1.54; // about -88 degrees, just short of -90, in radians
+const MAX_PITCH: f32 = 1.54;
+
+/// Orbit/zoom/pan camera for the interactive `--visible` preview.
+///
+/// The camera is stored as spherical coordinates (`radius`, `yaw`, `pitch`)
+/// around a `target` point rather than as a view matrix directly, since
+/// that's what makes drag-to-orbit and scroll-to-zoom simple incremental
+/// updates instead of matrix decomposition. Yaw/pitch are measured relative
+/// to an orthonormal frame built from `Config::camera_up` - `chunk0-3` made
+/// the up vector configurable, so this can't just assume world-Z is "up"
+/// the way a fixed-axis spherical system normally would.
+pub struct Controls {
+    pub radius: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub target: Vector3<f32>,
+    up: Vector3<f32>,
+    // Orthonormal basis spanning the plane perpendicular to `up`, anchored
+    // to the initial camera direction so that `yaw == 0.0` reproduces the
+    // eye position `Controls::new` was built with.
+    ref_forward: Vector3<f32>,
+    ref_right: Vector3<f32>,
+    min_radius: f32,
+    max_radius: f32,
+}
+
+impl Controls {
+    /// Builds controls that reproduce the given eye/target/up as their
+    /// initial spherical position. `up` is whatever `Config::camera_up` was
+    /// set to; yaw/pitch are measured relative to it rather than to +Z.
+    pub fn new(
+        eye: Point3<f32>,
+        target: Point3<f32>,
+        up: Vector3<f32>,
+        min_radius: f32,
+        max_radius: f32,
+    ) -> Controls {
+        let up = up.normalize();
+        let offset = eye - target;
+        let radius = offset.magnitude();
+
+        let height = offset.dot(up);
+        let pitch = if radius > 0.0 {
+            (height / radius).asin()
+        } else {
+            0.0
+        };
+
+        // The component of `offset` perpendicular to `up` is the zero-yaw
+        // direction. If the initial eye sits right on the up axis (looking
+        // straight down it), fall back to an arbitrary direction
+        // perpendicular to `up` instead of normalizing a ~zero vector.
+        let planar = offset - up * height;
+        let ref_forward = if planar.magnitude2() > 1e-12 {
+            planar.normalize()
+        } else {
+            arbitrary_perpendicular(up)
+        };
+        let ref_right = up.cross(ref_forward).normalize();
+
+        Controls {
+            radius,
+            yaw: 0.0,
+            pitch,
+            target: target.to_vec(),
+            up,
+            ref_forward,
+            ref_right,
+            min_radius,
+            max_radius,
+        }
+    }
+
+    /// Rotates the camera around `target`. `dyaw`/`dpitch` are angle deltas
+    /// in radians; the caller converts mouse-drag pixels with its own
+    /// sensitivity.
+    pub fn orbit(&mut self, dyaw: f32, dpitch: f32) {
+        self.yaw -= dyaw;
+        self.pitch = (self.pitch + dpitch).max(MIN_PITCH).min(MAX_PITCH);
+    }
+
+    /// Moves the camera towards (negative) or away from (positive) `target`.
+    pub fn zoom(&mut self, delta: f32) {
+        self.radius = (self.radius + delta).max(self.min_radius).min(self.max_radius);
+    }
+
+    /// Slides `target` sideways/up in the camera's own screen plane.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let forward = self.eye_offset().normalize();
+        let right = forward.cross(self.up).normalize();
+        let up = right.cross(forward).normalize();
+        self.target += right * dx + up * dy;
+    }
+
+    fn eye_offset(&self) -> Vector3<f32> {
+        let equatorial = self.ref_forward * self.yaw.cos() + self.ref_right * self.yaw.sin();
+        self.radius * (self.pitch.cos() * equatorial + self.pitch.sin() * self.up)
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        let target = Point3::from_vec(self.target);
+        let eye = target + self.eye_offset();
+        Matrix4::look_at(eye, target, self.up)
+    }
+}
+
+/// Any unit vector perpendicular to `up`, for the degenerate case where the
+/// initial camera direction is parallel to `up` and so has no planar
+/// component to derive one from.
+fn arbitrary_perpendicular(up: Vector3<f32>) -> Vector3<f32> {
+    let reference = if up.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+    reference.cross(up).normalize()
+}
@@ -0,0 +1,174 @@
+use std::rc::Rc;
+use glium::{self, Surface};
+use glium::backend::{Context, Facade};
+
+/// Fast Approximate Anti-Aliasing: render the scene to an intermediate
+/// texture, then run a single full-screen-quad pass that smooths edges by
+/// sampling neighboring pixels. Much cheaper than MSAA, at the cost of some
+/// fine detail.
+pub struct FxaaSystem {
+    context: Rc<Context>,
+    vertex_buffer: glium::VertexBuffer<SpriteVertex>,
+    index_buffer: glium::IndexBuffer<u16>,
+    program: glium::Program,
+}
+
+#[derive(Copy, Clone)]
+struct SpriteVertex {
+    position: [f32; 2],
+    i_tex_coords: [f32; 2],
+}
+implement_vertex!(SpriteVertex, position, i_tex_coords);
+
+impl FxaaSystem {
+    pub fn new<F: Facade>(display: &F) -> FxaaSystem {
+        FxaaSystem {
+            context: display.get_context().clone(),
+
+            vertex_buffer: glium::VertexBuffer::new(
+                display,
+                &[
+                    SpriteVertex { position: [-1.0, -1.0], i_tex_coords: [0.0, 0.0] },
+                    SpriteVertex { position: [-1.0, 1.0], i_tex_coords: [0.0, 1.0] },
+                    SpriteVertex { position: [1.0, 1.0], i_tex_coords: [1.0, 1.0] },
+                    SpriteVertex { position: [1.0, -1.0], i_tex_coords: [1.0, 0.0] },
+                ],
+            ).unwrap(),
+
+            index_buffer: glium::IndexBuffer::new(
+                display,
+                glium::index::PrimitiveType::TriangleStrip,
+                &[1u16, 2, 0, 3],
+            ).unwrap(),
+
+            program: program!(display,
+                140 => {
+                    vertex: "
+                        #version 140
+                        in vec2 position;
+                        in vec2 i_tex_coords;
+                        out vec2 v_tex_coords;
+
+                        void main() {
+                            gl_Position = vec4(position, 0.0, 1.0);
+                            v_tex_coords = i_tex_coords;
+                        }
+                    ",
+
+                    fragment: "
+                        #version 140
+                        in vec2 v_tex_coords;
+                        out vec4 f_color;
+
+                        uniform sampler2D tex;
+                        uniform vec2 resolution;
+
+                        const float FXAA_SPAN_MAX = 8.0;
+                        const float FXAA_REDUCE_MUL = 1.0 / 8.0;
+                        const float FXAA_REDUCE_MIN = 1.0 / 128.0;
+
+                        void main() {
+                            vec2 texel = 1.0 / resolution;
+
+                            vec3 rgb_nw = texture(tex, v_tex_coords + (vec2(-1.0, -1.0) * texel)).rgb;
+                            vec3 rgb_ne = texture(tex, v_tex_coords + (vec2(1.0, -1.0) * texel)).rgb;
+                            vec3 rgb_sw = texture(tex, v_tex_coords + (vec2(-1.0, 1.0) * texel)).rgb;
+                            vec3 rgb_se = texture(tex, v_tex_coords + (vec2(1.0, 1.0) * texel)).rgb;
+                            vec4 rgba_m = texture(tex, v_tex_coords);
+                            vec3 rgb_m = rgba_m.rgb;
+
+                            vec3 luma = vec3(0.299, 0.587, 0.114);
+                            float luma_nw = dot(rgb_nw, luma);
+                            float luma_ne = dot(rgb_ne, luma);
+                            float luma_sw = dot(rgb_sw, luma);
+                            float luma_se = dot(rgb_se, luma);
+                            float luma_m = dot(rgb_m, luma);
+
+                            float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+                            float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+                            vec2 dir = vec2(
+                                -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+                                (luma_nw + luma_sw) - (luma_ne + luma_se)
+                            );
+
+                            float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+                            float inv_dir_adjustment = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+
+                            dir = min(vec2(FXAA_SPAN_MAX, FXAA_SPAN_MAX),
+                                max(vec2(-FXAA_SPAN_MAX, -FXAA_SPAN_MAX), dir * inv_dir_adjustment)) * texel;
+
+                            vec3 rgb_a = 0.5 * (
+                                texture(tex, v_tex_coords + dir * (1.0 / 3.0 - 0.5)).rgb +
+                                texture(tex, v_tex_coords + dir * (2.0 / 3.0 - 0.5)).rgb);
+
+                            vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+                                texture(tex, v_tex_coords + dir * -0.5).rgb +
+                                texture(tex, v_tex_coords + dir * 0.5).rgb);
+
+                            float luma_b = dot(rgb_b, luma);
+
+                            if (luma_b < luma_min || luma_b > luma_max) {
+                                f_color = vec4(rgb_a, rgba_m.a);
+                            } else {
+                                f_color = vec4(rgb_b, rgba_m.a);
+                            }
+                        }
+                    ",
+                },
+            ).unwrap(),
+        }
+    }
+
+    fn scene_texture(&self, width: u32, height: u32) -> (glium::texture::Texture2d, glium::texture::DepthTexture2d) {
+        (
+            glium::texture::Texture2d::empty(&self.context, width, height).unwrap(),
+            glium::texture::DepthTexture2d::empty(&self.context, width, height).unwrap(),
+        )
+    }
+}
+
+/// Draws the scene produced by `draw_scene` into `target`, running it
+/// through the FXAA resolve pass first when `enabled` is true. When disabled,
+/// `draw_scene` renders straight into `target`.
+pub fn draw<'a, F>(
+    system: &FxaaSystem,
+    target: &mut glium::framebuffer::SimpleFrameBuffer<'a>,
+    enabled: bool,
+    draw_scene: F,
+) where
+    F: FnOnce(&mut glium::framebuffer::SimpleFrameBuffer),
+{
+    if !enabled {
+        draw_scene(target);
+        return;
+    }
+
+    let (width, height) = target.get_dimensions();
+    let (color_texture, depth_texture) = system.scene_texture(width, height);
+    {
+        let mut scene_fb = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+            &system.context,
+            &color_texture,
+            &depth_texture,
+        ).unwrap();
+        draw_scene(&mut scene_fb);
+    }
+
+    let uniforms = uniform! {
+        tex: color_texture.sampled()
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
+            .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest),
+        resolution: (width as f32, height as f32),
+    };
+
+    target
+        .draw(
+            &system.vertex_buffer,
+            &system.index_buffer,
+            &system.program,
+            &uniforms,
+            &Default::default(),
+        )
+        .unwrap();
+}
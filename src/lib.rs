@@ -5,23 +5,62 @@ extern crate image;
 #[macro_use]
 extern crate log;
 extern crate mint;
+extern crate stl_io;
 
 pub mod config;
 mod mesh;
 mod fxaa;
+mod controls;
 
 use std::error::Error;
 use std::fs::File;
+use std::rc::Rc;
 use std::{io, thread, time};
 use config::Config;
-use cgmath::EuclideanSpace;
+use cgmath::{EuclideanSpace, InnerSpace};
 use glium::{glutin, Surface, CapabilitiesSource};
+use glium::backend::{Context, Facade};
+use image::GenericImageView;
 use mesh::Mesh;
+use controls::Controls;
 
 // TODO: Move this stuff to config module
 const BACKGROUND_COLOR: (f32, f32, f32, f32) = (1.0, 1.0, 1.0, 0.0);
-const CAM_FOV_DEG: f32 = 30.0;
-const CAM_POSITION: cgmath::Point3<f32> = cgmath::Point3 {x: 2.0, y: -4.0, z: 2.0};
+
+// Mouse sensitivity for the interactive preview (--visible). Pan speed also
+// scales with the current orbit radius so it feels consistent whether
+// zoomed in or out.
+const ORBIT_SPEED: f32 = 0.005;
+const PAN_SPEED: f32 = 0.0015;
+const ZOOM_SPEED: f32 = 0.02;
+
+/// Camera distance such that a bounding sphere of `radius` tightly fills a
+/// frustum with the given vertical FOV and aspect ratio. Uses whichever axis
+/// (vertical or horizontal) is tighter, so the sphere fits on both.
+fn auto_frame_distance(radius: f32, fov_deg: f32, aspect: f32) -> f32 {
+    let half_v = cgmath::Rad::from(cgmath::Deg(fov_deg)).0 / 2.0;
+    let half_h = (aspect * half_v.tan()).atan();
+    let half_angle = half_v.min(half_h);
+    radius / half_angle.sin()
+}
+
+/// Resizes `img` to exactly `target_width`x`target_height` by scaling to
+/// cover (one dimension matches the target exactly, the other is at least
+/// as large) and then center-cropping the overflow, instead of stretching with
+/// `resize_exact`. This is what lets a caller request outputs of different
+/// aspect ratios (e.g. a square icon and a wide banner) from the same
+/// render without anything coming out squashed.
+fn resize_cover(img: &image::DynamicImage, target_width: u32, target_height: u32) -> image::DynamicImage {
+    let (src_width, src_height) = img.dimensions();
+    let scale = (target_width as f32 / src_width as f32).max(target_height as f32 / src_height as f32);
+    let scaled_width = ((src_width as f32 * scale).round() as u32).max(target_width);
+    let scaled_height = ((src_height as f32 * scale).round() as u32).max(target_height);
+
+    let mut scaled = img.resize_exact(scaled_width, scaled_height, image::FilterType::Lanczos3);
+    let crop_x = (scaled_width - target_width) / 2;
+    let crop_y = (scaled_height - target_height) / 2;
+    scaled.crop(crop_x, crop_y, target_width, target_height)
+}
 
 
 struct Material {
@@ -39,6 +78,60 @@ fn print_matrix(m: [[f32; 4]; 4]) {
 }
 
 
+/// The two ways we can end up with a GL context: a real window (for
+/// `--visible`), or a surfaceless one for everything else. We never need to
+/// present a `Backend::Headless` to a screen, so it only has to implement
+/// `Facade` - that's enough to create buffers, textures and programs and to
+/// render into our own off-screen framebuffer.
+enum Backend {
+    Windowed(glium::Display),
+    Headless(glium::HeadlessRenderer),
+}
+
+impl Backend {
+    fn context(&self) -> &Rc<Context> {
+        match *self {
+            Backend::Windowed(ref display) => display.get_context(),
+            Backend::Headless(ref renderer) => renderer.get_context(),
+        }
+    }
+}
+
+impl Facade for Backend {
+    fn get_context(&self) -> &Rc<Context> {
+        self.context()
+    }
+}
+
+fn context_builder(samples: u16) -> glutin::ContextBuilder<'static> {
+    let builder = glutin::ContextBuilder::new().with_depth_buffer(24);
+    if samples > 0 {
+        builder.with_multisampling(samples)
+    } else {
+        builder
+    }
+}
+
+/// Builds a context with no window or display surface at all, backed
+/// directly by EGL. This is what lets stl-thumb run as a thumbnailer on
+/// headless servers and CI where no X11/Wayland display is attached.
+/// Falls back to no multisampling if `samples` isn't supported, and returns
+/// whichever sample count was actually obtained.
+fn build_headless(events_loop: &glutin::EventsLoop, samples: u16) -> (glium::HeadlessRenderer, u16) {
+    let (context, samples) = match context_builder(samples).build_surfaceless(events_loop) {
+        Ok(context) => (context, samples),
+        Err(_) => {
+            warn!("MSAA x{} unsupported in headless mode, falling back", samples);
+            let context = context_builder(0)
+                .build_surfaceless(events_loop)
+                .expect("Couldn't create a surfaceless EGL context for headless rendering");
+            (context, 0)
+        }
+    };
+    (glium::HeadlessRenderer::new(context).expect("Couldn't create headless renderer"), samples)
+}
+
+
 pub fn run(config: &Config) -> Result<(), Box<Error>> {
     // Create geometry from STL file
     // =========================
@@ -56,33 +149,75 @@ pub fn run(config: &Config) -> Result<(), Box<Error>> {
     // -----------------
 
     let mut events_loop = glutin::EventsLoop::new();
-    let window_dim = glutin::dpi::LogicalSize::new(
-        config.width.into(),
-        config.height.into());
-    let window = glutin::WindowBuilder::new()
-        .with_title("stl-thumb")
-        .with_dimensions(window_dim)
-        .with_min_dimensions(window_dim)
-        .with_max_dimensions(window_dim)
-        .with_visibility(config.visible);
-    let context = glutin::ContextBuilder::new()
-        .with_depth_buffer(24);
-        //.with_multisampling(8);
-        //.with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGlEs, (2, 0)));
-    let display = glium::Display::new(window, context, &events_loop).unwrap();
-    //let context = glutin::HeadlessRendererBuilder::new(config.width, config.height)
-    //    //.with_depth_buffer(24)
-    //    .build().unwrap();
-    //let display = glium::HeadlessRenderer::new(context).unwrap();
+
+    let requested_samples = config.antialiasing.msaa_samples();
+
+    fn build_window(width: u32, height: u32) -> glutin::WindowBuilder {
+        let window_dim = glutin::dpi::LogicalSize::new(width.into(), height.into());
+        glutin::WindowBuilder::new()
+            .with_title("stl-thumb")
+            .with_dimensions(window_dim)
+            .with_min_dimensions(window_dim)
+            .with_max_dimensions(window_dim)
+            .with_visibility(true)
+    }
+
+    // Render once at the largest requested output size; every smaller
+    // output is produced later by resizing this one, rather than
+    // re-rendering per size.
+    let (render_width, render_height) = config
+        .outputs
+        .iter()
+        .map(|o| (o.width, o.height))
+        .max_by_key(|&(w, h)| w as u64 * h as u64)
+        .expect("Config.outputs must not be empty");
+
+    // The pinned `image` crate can decode WebP but not encode it, so a WebP
+    // output would otherwise only fail once we get to `write_to` below.
+    // Caller-supplied `Config` data is not something we should panic on, so
+    // reject it up front as a normal error.
+    for target in &config.outputs {
+        if target.format == image::ImageFormat::WEBP {
+            return Err(From::from(
+                "WebP output is not supported: this crate's image decoder has no WebP encoder",
+            ));
+        }
+    }
+
+    // Headless unless the caller actually wants to see a window: a visible
+    // window needs a real WindowedContext to present to, but the common case
+    // (thumbnailer daemon, CI) has no display attached at all.
+    let (display, msaa_samples) = if config.visible {
+        match glium::Display::new(
+            build_window(render_width, render_height),
+            context_builder(requested_samples),
+            &events_loop,
+        ) {
+            Ok(d) => (Backend::Windowed(d), requested_samples),
+            Err(_) => {
+                warn!("MSAA x{} unsupported, falling back", requested_samples);
+                let d = glium::Display::new(
+                    build_window(render_width, render_height),
+                    context_builder(0),
+                    &events_loop,
+                ).unwrap();
+                (Backend::Windowed(d), 0)
+            }
+        }
+    } else {
+        let (renderer, samples) = build_headless(&events_loop, requested_samples);
+        (Backend::Headless(renderer), samples)
+    };
 
     // Print context information
-    info!("GL Version:   {:?}", display.get_opengl_version());
-    info!("GL Version:   {}", display.get_opengl_version_string());
-    info!("GLSL Version: {:?}", display.get_supported_glsl_version());
-    info!("Vendor:       {}", display.get_opengl_vendor_string());
-    info!("Renderer      {}", display.get_opengl_renderer_string());
-    info!("Free GPU Mem: {:?}", display.get_free_video_memory());
-    info!("Depth Bits:   {:?}\n", display.get_capabilities().depth_bits);
+    let gl_context = display.context();
+    info!("GL Version:   {:?}", gl_context.get_opengl_version());
+    info!("GL Version:   {}", gl_context.get_opengl_version_string());
+    info!("GLSL Version: {:?}", gl_context.get_supported_glsl_version());
+    info!("Vendor:       {}", gl_context.get_opengl_vendor_string());
+    info!("Renderer      {}", gl_context.get_opengl_renderer_string());
+    info!("Free GPU Mem: {:?}", gl_context.get_free_video_memory());
+    info!("Depth Bits:   {:?}\n", gl_context.get_capabilities().depth_bits);
 
 
     let params = glium::DrawParameters {
@@ -117,6 +252,7 @@ pub fn run(config: &Config) -> Result<(), Box<Error>> {
 
     let vertex_buf = glium::VertexBuffer::new(&display, &mesh.vertices).unwrap();
     let normal_buf = glium::VertexBuffer::new(&display, &mesh.normals).unwrap();
+    let barycentric_buf = glium::VertexBuffer::new(&display, &mesh.barycentric).unwrap();
     // Can use NoIndices here because STLs are dumb
     let indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
 
@@ -124,22 +260,38 @@ pub fn run(config: &Config) -> Result<(), Box<Error>> {
     // --------------
 
     // Transformation matrix (positions, scales and rotates model)
-    let transform_matrix = mesh.scale_and_center();
+    let framing = mesh.scale_and_center();
+    let transform_matrix = framing.transform;
 
     // View matrix (convert to positions relative to camera)
-    // TODO: View matrix never changes. We could bake this at compile time and save a
-    // little processing.
-    let view_matrix = cgmath::Matrix4::look_at(CAM_POSITION, cgmath::Point3::origin(), cgmath::Vector3::unit_z());
+    let camera_target = cgmath::Point3::from(config.camera_target);
+    let camera_up = cgmath::Vector3::from(config.camera_up);
+    let aspect = render_width as f32 / render_height as f32;
+
+    let camera_position = if config.auto_frame {
+        let direction = (cgmath::Point3::from(config.camera_position) - camera_target)
+            .normalize();
+        let distance = auto_frame_distance(framing.bounding_radius, config.fov_deg, aspect);
+        camera_target + direction * distance
+    } else {
+        cgmath::Point3::from(config.camera_position)
+    };
+
+    let view_matrix = cgmath::Matrix4::look_at(camera_position, camera_target, camera_up);
     debug!("View:");
     print_matrix(view_matrix.into());
 
-    // Perspective matrix (give illusion of depth)
-    let perspective_matrix = cgmath::perspective(
-        cgmath::Deg(CAM_FOV_DEG),
-        config.width as f32 / config.height as f32,
-        0.1,
-        1024.0,
-    );
+    // Perspective matrix (give illusion of depth), unless the caller
+    // supplied their own or asked for an orthographic projection.
+    let perspective_matrix = match config.projection_matrix {
+        Some(m) => m.into(),
+        None if config.orthographic => {
+            let half_height = framing.bounding_radius;
+            let half_width = half_height * aspect;
+            cgmath::ortho(-half_width, half_width, -half_height, half_height, 0.1, 1024.0)
+        }
+        None => cgmath::perspective(cgmath::Deg(config.fov_deg), aspect, 0.1, 1024.0),
+    };
     debug!("Perspective:");
     print_matrix(perspective_matrix.into());
 
@@ -154,74 +306,176 @@ pub fn run(config: &Config) -> Result<(), Box<Error>> {
         specular: [1.0, 1.0, 1.0],
     };
 
-    let uniforms = uniform! {
-        model: Into::<[[f32; 4]; 4]>::into(transform_matrix),
-        view: Into::<[[f32; 4]; 4]>::into(view_matrix),
-        perspective: Into::<[[f32; 4]; 4]>::into(perspective_matrix),
-        u_light: light_dir,
-        ambient_color: colors.ambient,
-        diffuse_color: colors.diffuse,
-        specular_color: colors.specular,
-    };
+    // Wireframe overlay color (rendered over the shaded surface)
+    let wireframe_color = [0.0, 0.0, 0.0f32];
 
     // Draw
     // ----
 
     // Create off screen texture to render to
-    let texture = glium::Texture2d::empty(&display, config.width, config.height).unwrap();
-    let depthtexture = glium::texture::DepthTexture2d::empty(&display, config.width, config.height).unwrap();
+    let texture = glium::Texture2d::empty(&display, render_width, render_height).unwrap();
+    let depthtexture = glium::texture::DepthTexture2d::empty(&display, render_width, render_height).unwrap();
     let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display, &texture, &depthtexture).unwrap();
 
-    // Create FXAA system
-    let fxaa = fxaa::FxaaSystem::new(&display);
+    // Antialiasing resources that must not be rebuilt per frame: compiling
+    // the FXAA program or reallocating the MSAA renderbuffers is fine for a
+    // single still render, but the interactive preview calls `render_frame`
+    // on essentially every mouse-move event while orbiting, so these are
+    // built once up front and reused by every call below.
+    let fxaa_system = match config.antialiasing {
+        config::Antialiasing::Fxaa => Some(fxaa::FxaaSystem::new(&display)),
+        _ => None,
+    };
+    let msaa_target = match config.antialiasing {
+        config::Antialiasing::Msaa(_) if msaa_samples > 0 => {
+            let ms_color = glium::texture::Texture2dMultisample::empty(
+                &display, render_width, render_height, msaa_samples as u32);
+            let ms_depth = glium::texture::DepthTexture2dMultisample::empty(
+                &display, render_width, render_height, msaa_samples as u32);
+            match (ms_color, ms_depth) {
+                (Ok(ms_color), Ok(ms_depth)) => Some((ms_color, ms_depth)),
+                _ => {
+                    warn!("MSAA x{} render target unsupported, falling back", msaa_samples);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
 
-    fxaa::draw(&fxaa, &mut framebuffer, true, |target| {
-        // Fills background color and clears depth buffer
-        target.clear_color_and_depth(BACKGROUND_COLOR, 1.0);
-        target.draw((&vertex_buf, &normal_buf), &indices, &program, &uniforms, &params)
-            .unwrap();
-        // TODO: Shadows
-    });
+    // Renders one frame with the given view matrix into `framebuffer`.
+    // Rebuilding the uniforms each call is cheap, and is what lets the
+    // interactive preview re-render after every orbit/zoom/pan update.
+    let render_frame = |view_matrix: cgmath::Matrix4<f32>, framebuffer: &mut glium::framebuffer::SimpleFrameBuffer| {
+        let uniforms = uniform! {
+            model: Into::<[[f32; 4]; 4]>::into(transform_matrix),
+            view: Into::<[[f32; 4]; 4]>::into(view_matrix),
+            perspective: Into::<[[f32; 4]; 4]>::into(perspective_matrix),
+            u_light: light_dir,
+            ambient_color: colors.ambient,
+            diffuse_color: colors.diffuse,
+            specular_color: colors.specular,
+            wireframe: config.wireframe,
+            wireframe_blend: config.wireframe_blend,
+            wireframe_color: wireframe_color,
+        };
 
-    // Save Image
-    // ==========
+        let draw_scene = |target: &mut glium::framebuffer::SimpleFrameBuffer| {
+            // Fills background color and clears depth buffer
+            target.clear_color_and_depth(BACKGROUND_COLOR, 1.0);
+            target.draw((&vertex_buf, &normal_buf, &barycentric_buf), &indices, &program, &uniforms, &params)
+                .unwrap();
+            // TODO: Shadows
+        };
+
+        match fxaa_system {
+            // FXAA: a fullscreen post-process pass smooths edges after the fact.
+            Some(ref fxaa) => fxaa::draw(fxaa, framebuffer, true, draw_scene),
+            None => match msaa_target {
+                // MSAA: render into a multisampled color/depth target, then resolve
+                // (blit) down into the single-sample texture we read back from.
+                Some((ref ms_color, ref ms_depth)) => {
+                    let mut ms_framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+                        &display, ms_color, ms_depth).unwrap();
+                    draw_scene(&mut ms_framebuffer);
+
+                    let rect = glium::Rect { left: 0, bottom: 0, width: render_width, height: render_height };
+                    let blit_target = glium::BlitTarget {
+                        left: 0, bottom: 0, width: render_width as i32, height: render_height as i32 };
+                    framebuffer.blit_from_simple_framebuffer(
+                        &ms_framebuffer, &rect, &blit_target, glium::uniforms::MagnifySamplerFilter::Nearest);
+                }
+                // No antialiasing (or MSAA fell back to unsupported): draw straight in.
+                None => draw_scene(framebuffer),
+            },
+        }
+    };
+
+    render_frame(view_matrix, &mut framebuffer);
+
+    // Save Images
+    // ===========
 
     let pixels: glium::texture::RawImage2d<u8> = texture.read();
-    let img = image::ImageBuffer::from_raw(config.width, config.height, pixels.data.into_owned()).unwrap();
+    let img = image::ImageBuffer::from_raw(render_width, render_height, pixels.data.into_owned()).unwrap();
     let img = image::DynamicImage::ImageRgba8(img).flipv();
-    // Write to stdout if user did not specify a file
-    let mut output: Box<io::Write> = match config.img_filename {
-        Some(ref x) => {
-            Box::new(std::fs::File::create(&x).unwrap())
-        },
-        None => Box::new(io::stdout()),
-    };
-    img.write_to(&mut output, image::ImageFormat::PNG)
-        .expect("Error saving image");
+
+    // Only the largest output was actually rendered; every other requested
+    // size is produced from that one render, so we never pay for a second
+    // pass through the GL pipeline. Targets whose aspect ratio doesn't match
+    // the render are cover-resized (scaled to fill, then center-cropped)
+    // rather than stretched, so e.g. a square icon and a wide banner can be
+    // requested from the same invocation.
+    for target in &config.outputs {
+        let resized;
+        let out_img = if target.width == render_width && target.height == render_height {
+            &img
+        } else {
+            resized = resize_cover(&img, target.width, target.height);
+            &resized
+        };
+
+        // Write to stdout if the caller did not specify a file
+        let mut output: Box<io::Write> = match target.filename {
+            Some(ref filename) => Box::new(File::create(filename).unwrap()),
+            None => Box::new(io::stdout()),
+        };
+        out_img.write_to(&mut output, target.format)
+            .expect("Error saving image");
+    }
 
     // Wait until window is closed
     // ===========================
 
     if config.visible {
+        // config.visible always builds a Backend::Windowed above.
+        let window_display = match display {
+            Backend::Windowed(ref d) => d,
+            Backend::Headless(_) => unreachable!("visible mode always uses a windowed backend"),
+        };
+
+        let mut controls = Controls::new(
+            camera_position,
+            camera_target,
+            camera_up,
+            framing.bounding_radius * 0.05,
+            framing.bounding_radius * 50.0,
+        );
+
+        let mut left_dragging = false;
+        let mut right_dragging = false;
+        let mut shift_down = false;
+        let mut last_cursor = (0.0f64, 0.0f64);
+        // The static frame rendered above is already sitting in
+        // `framebuffer`, so nothing needs redrawing until the user moves
+        // something.
+        let mut dirty = false;
+
         let mut closed = false;
         let sleep_time = time::Duration::from_millis(10);
         while !closed {
             thread::sleep(sleep_time);
+
+            if dirty {
+                render_frame(controls.view_matrix(), &mut framebuffer);
+                dirty = false;
+            }
+
             // Copy framebuffer to display
             // TODO: I think theres some screwy srgb stuff going on here
-            let target = display.draw();
+            let target = window_display.draw();
             target.blit_from_simple_framebuffer(&framebuffer,
                                                 &glium::Rect {
                                                     left: 0,
                                                     bottom: 0,
-                                                    width: config.width,
-                                                    height: config.height,
+                                                    width: render_width,
+                                                    height: render_height,
                                                 },
                                                 &glium::BlitTarget {
                                                     left: 0,
                                                     bottom: 0,
-                                                    width: config.width as i32,
-                                                    height: config.height as i32,
+                                                    width: render_width as i32,
+                                                    height: render_height as i32,
                                                 },
                                                 glium::uniforms::MagnifySamplerFilter::Nearest);
             target.finish().unwrap();
@@ -231,6 +485,46 @@ pub fn run(config: &Config) -> Result<(), Box<Error>> {
                     glutin::Event::WindowEvent { event, .. } => match event {
                         glutin::WindowEvent::CloseRequested => closed = true,
                         glutin::WindowEvent::Destroyed => closed = true,
+                        glutin::WindowEvent::KeyboardInput { input, .. } => {
+                            if let Some(key) = input.virtual_keycode {
+                                if key == glutin::VirtualKeyCode::LShift
+                                    || key == glutin::VirtualKeyCode::RShift
+                                {
+                                    shift_down = input.state == glutin::ElementState::Pressed;
+                                }
+                            }
+                        }
+                        glutin::WindowEvent::MouseInput { state, button, .. } => {
+                            let pressed = state == glutin::ElementState::Pressed;
+                            match button {
+                                glutin::MouseButton::Left => left_dragging = pressed,
+                                glutin::MouseButton::Right => right_dragging = pressed,
+                                _ => (),
+                            }
+                        }
+                        glutin::WindowEvent::CursorMoved { position, .. } => {
+                            let (x, y): (f64, f64) = position.into();
+                            let (dx, dy) = (x - last_cursor.0, y - last_cursor.1);
+                            last_cursor = (x, y);
+
+                            if left_dragging && !shift_down {
+                                controls.orbit(dx as f32 * ORBIT_SPEED, dy as f32 * ORBIT_SPEED);
+                                dirty = true;
+                            } else if right_dragging || (left_dragging && shift_down) {
+                                let radius = controls.radius;
+                                controls.pan(-dx as f32 * PAN_SPEED * radius, dy as f32 * PAN_SPEED * radius);
+                                dirty = true;
+                            }
+                        }
+                        glutin::WindowEvent::MouseWheel { delta, .. } => {
+                            let scroll = match delta {
+                                glutin::MouseScrollDelta::LineDelta(_, y) => y,
+                                glutin::MouseScrollDelta::PixelDelta(pos) => (pos.y / 20.0) as f32,
+                            };
+                            let radius = controls.radius;
+                            controls.zoom(-scroll * ZOOM_SPEED * radius);
+                            dirty = true;
+                        }
                         _ => (),
                     },
                     _ => (),
@@ -254,13 +548,28 @@ mod tests {
     fn cube() {
         let config = Config {
             stl_filename: "test_data/cube.stl".to_string(),
-            img_filename: "cube.png".to_string(),
-            width: 1024,
-            height: 768,
+            outputs: vec![config::OutputTarget {
+                filename: Some("cube.png".to_string()),
+                width: 1024,
+                height: 768,
+                format: image::ImageFormat::PNG,
+            }],
             visible: false,
+            wireframe: false,
+            wireframe_blend: 0.0,
+            camera_position: [2.0, -4.0, 2.0],
+            camera_target: [0.0, 0.0, 0.0],
+            camera_up: [0.0, 0.0, 1.0],
+            fov_deg: 30.0,
+            orthographic: false,
+            projection_matrix: None,
+            auto_frame: false,
+            antialiasing: config::Antialiasing::Fxaa,
         };
 
-        match fs::remove_file(&config.img_filename) {
+        let img_filename = config.outputs[0].filename.clone().unwrap();
+
+        match fs::remove_file(&img_filename) {
             Ok(_) => (),
             Err(ref error) if error.kind() == ErrorKind::NotFound => (),
             Err(_) => {
@@ -270,10 +579,90 @@ mod tests {
 
         run(&config).expect("Error in run function");
 
-        let size = fs::metadata(config.img_filename)
+        let size = fs::metadata(img_filename)
             .expect("No file created")
             .len();
 
         assert_ne!(0, size);
     }
+
+    #[test]
+    fn auto_frame_distance_square_aspect() {
+        // At a 90 degree FOV and aspect 1.0, both axes are equally tight, so
+        // the bounding sphere fills the frame exactly at distance
+        // radius / sin(45 degrees).
+        let radius = 2.0;
+        let distance = auto_frame_distance(radius, 90.0, 1.0);
+        let expected = radius / (std::f32::consts::FRAC_PI_4).sin();
+        assert!((distance - expected).abs() < 1e-4, "{} != {}", distance, expected);
+    }
+
+    #[test]
+    fn auto_frame_distance_widening_aspect_keeps_tighter_axis() {
+        // Widening the aspect ratio only relaxes the horizontal FOV; the
+        // vertical FOV stays the tighter constraint, so the distance should
+        // not change.
+        let radius = 1.0;
+        let narrow = auto_frame_distance(radius, 30.0, 1.0);
+        let wide = auto_frame_distance(radius, 30.0, 4.0);
+        assert!((narrow - wide).abs() < 1e-4, "{} != {}", narrow, wide);
+    }
+
+    #[test]
+    fn multiple_outputs_with_different_aspect_ratios() {
+        // One square and one wide/short output from the same render: since
+        // only the square's aspect ratio is actually rendered, the wide one
+        // exercises the cover-resize path instead of a plain resize.
+        let config = Config {
+            stl_filename: "test_data/cube.stl".to_string(),
+            outputs: vec![
+                config::OutputTarget {
+                    filename: Some("cube_multi_square.png".to_string()),
+                    width: 256,
+                    height: 256,
+                    format: image::ImageFormat::PNG,
+                },
+                config::OutputTarget {
+                    filename: Some("cube_multi_wide.png".to_string()),
+                    width: 128,
+                    height: 64,
+                    format: image::ImageFormat::PNG,
+                },
+            ],
+            visible: false,
+            wireframe: false,
+            wireframe_blend: 0.0,
+            camera_position: [2.0, -4.0, 2.0],
+            camera_target: [0.0, 0.0, 0.0],
+            camera_up: [0.0, 0.0, 1.0],
+            fov_deg: 30.0,
+            orthographic: false,
+            projection_matrix: None,
+            auto_frame: false,
+            antialiasing: config::Antialiasing::Fxaa,
+        };
+
+        for target in &config.outputs {
+            let filename = target.filename.as_ref().unwrap();
+            match fs::remove_file(filename) {
+                Ok(_) => (),
+                Err(ref error) if error.kind() == ErrorKind::NotFound => (),
+                Err(_) => panic!("Couldn't clean files before testing"),
+            }
+        }
+
+        run(&config).expect("Error in run function");
+
+        for target in &config.outputs {
+            let filename = target.filename.as_ref().unwrap();
+            let img = image::open(filename).expect("No file created");
+            assert_eq!(img.dimensions(), (target.width, target.height));
+        }
+
+        // Different sizes of the same render should never collapse to
+        // identical bytes.
+        let square = fs::read("cube_multi_square.png").unwrap();
+        let wide = fs::read("cube_multi_wide.png").unwrap();
+        assert_ne!(square, wide);
+    }
 }
@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::io::Read;
+
+use cgmath::{InnerSpace, Matrix4, Vector3};
+
+#[derive(Copy, Clone)]
+pub struct Vertex {
+    pub position: [f32; 3],
+}
+implement_vertex!(Vertex, position);
+
+#[derive(Copy, Clone)]
+pub struct Normal {
+    pub normal: [f32; 3],
+}
+implement_vertex!(Normal, normal);
+
+/// One corner of a triangle, tagged (1,0,0)/(0,1,0)/(0,0,1) by position
+/// within the triangle. Interpolating this across a triangle and looking at
+/// how close it gets to zero on any axis tells the fragment shader how close
+/// a pixel is to an edge, which is how the wireframe overlay is drawn.
+#[derive(Copy, Clone)]
+pub struct Barycentric {
+    pub barycentric: [f32; 3],
+}
+implement_vertex!(Barycentric, barycentric);
+
+const TRIANGLE_CORNERS: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// What `Mesh::scale_and_center()` did to the mesh: the transform itself,
+/// plus the radius of the bounding sphere in the transformed (unit-box)
+/// space, which callers use to auto-frame the camera.
+pub struct Framing {
+    pub transform: Matrix4<f32>,
+    pub bounding_radius: f32,
+}
+
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub normals: Vec<Normal>,
+    pub barycentric: Vec<Barycentric>,
+}
+
+impl Mesh {
+    /// Parses an STL file into a flat, un-indexed triangle list. STLs store
+    /// three vertices per face with no sharing between faces, so we don't
+    /// bother building an index buffer - `NoIndices` is enough.
+    pub fn from_stl<R: Read>(mut file: R) -> Result<Mesh, Box<Error>> {
+        let stl = stl_io::read_stl(&mut file)?;
+
+        let mut vertices = Vec::with_capacity(stl.faces.len() * 3);
+        let mut normals = Vec::with_capacity(stl.faces.len() * 3);
+        let mut barycentric = Vec::with_capacity(stl.faces.len() * 3);
+
+        for triangle in &stl.faces {
+            let normal = Normal {
+                normal: [triangle.normal[0], triangle.normal[1], triangle.normal[2]],
+            };
+            for (corner, &i) in triangle.vertices.iter().enumerate() {
+                let v = stl.vertices[i];
+                vertices.push(Vertex {
+                    position: [v[0], v[1], v[2]],
+                });
+                normals.push(normal);
+                barycentric.push(Barycentric {
+                    barycentric: TRIANGLE_CORNERS[corner],
+                });
+            }
+        }
+
+        Ok(Mesh { vertices, normals, barycentric })
+    }
+
+    /// Computes a transform that centers the mesh on the origin and scales it
+    /// so its largest dimension fits in a 1x1x1 box, along with the bounding
+    /// sphere radius in that same transformed space (for auto-framing).
+    pub fn scale_and_center(&self) -> Framing {
+        let mut min = [std::f32::MAX; 3];
+        let mut max = [std::f32::MIN; 3];
+
+        for v in &self.vertices {
+            for i in 0..3 {
+                if v.position[i] < min[i] {
+                    min[i] = v.position[i];
+                }
+                if v.position[i] > max[i] {
+                    max[i] = v.position[i];
+                }
+            }
+        }
+
+        let center = Vector3::new(
+            (min[0] + max[0]) / 2.0,
+            (min[1] + max[1]) / 2.0,
+            (min[2] + max[2]) / 2.0,
+        );
+
+        let size = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+        let max_dim = size[0].max(size[1]).max(size[2]);
+        let scale = if max_dim > 0.0 { 1.0 / max_dim } else { 1.0 };
+
+        let bounding_radius = self
+            .vertices
+            .iter()
+            .map(|v| (Vector3::from(v.position) - center).magnitude())
+            .fold(0.0f32, f32::max)
+            * scale;
+
+        Framing {
+            transform: Matrix4::from_scale(scale) * Matrix4::from_translation(-center),
+            bounding_radius,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: [f32; 3]) -> Vertex {
+        Vertex { position }
+    }
+
+    #[test]
+    fn scale_and_center_bounding_radius_of_cube() {
+        // An axis-aligned cube from -1..1 has its largest dimension (2.0)
+        // scaled down to 1.0, so its half-diagonal (sqrt(3)) bounding
+        // radius shrinks by the same 0.5 factor.
+        let mesh = Mesh {
+            vertices: vec![
+                vertex([-1.0, -1.0, -1.0]),
+                vertex([1.0, -1.0, -1.0]),
+                vertex([-1.0, 1.0, -1.0]),
+                vertex([1.0, 1.0, -1.0]),
+                vertex([-1.0, -1.0, 1.0]),
+                vertex([1.0, -1.0, 1.0]),
+                vertex([-1.0, 1.0, 1.0]),
+                vertex([1.0, 1.0, 1.0]),
+            ],
+            normals: vec![],
+            barycentric: vec![],
+        };
+
+        let framing = mesh.scale_and_center();
+        let expected = (3.0f32).sqrt() / 2.0;
+        assert!(
+            (framing.bounding_radius - expected).abs() < 1e-5,
+            "{} != {}",
+            framing.bounding_radius,
+            expected
+        );
+    }
+}